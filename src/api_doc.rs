@@ -0,0 +1,23 @@
+use utoipa::OpenApi;
+
+use crate::models::{CreateLabel, CreateTodo, Label, State, Subtask, Todo, ToggleTask, UpdateTodo};
+
+/// Aggregates the handler paths and schemas exposed at `/api-docs/openapi.json`
+/// and rendered by the Swagger UI mounted at `/swagger-ui`.
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        crate::todos_index,
+        crate::todos_create,
+        crate::todos_update,
+        crate::todos_delete,
+        crate::todos_toggle_task,
+        crate::labels_index,
+        crate::labels_create,
+        crate::labels_delete,
+    ),
+    components(schemas(
+        Todo, CreateTodo, UpdateTodo, State, Subtask, ToggleTask, Label, CreateLabel
+    ))
+)]
+pub struct ApiDoc;
@@ -0,0 +1,81 @@
+use axum::{
+    Json,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use serde::Serialize;
+use serde_json::Value;
+
+/// Uniform error type returned by every handler and by the timeout/internal
+/// branches of the `HandleErrorLayer` in `main`, so every failure reaches the
+/// client as the same `{ "error": "...", "code": <status> }` envelope.
+#[derive(Debug)]
+pub enum AppError {
+    NotFound(String),
+    BadRequest(String),
+    Validation(validator::ValidationErrors),
+    Timeout,
+    Internal(String),
+}
+
+#[derive(Serialize)]
+struct ErrorBody {
+    /// A human-readable message for most errors, or the structured
+    /// per-field map `validator::ValidationErrors` serializes to.
+    error: Value,
+    code: u16,
+}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        let (status, error) = match self {
+            AppError::NotFound(message) => (StatusCode::NOT_FOUND, Value::String(message)),
+            AppError::BadRequest(message) => (StatusCode::BAD_REQUEST, Value::String(message)),
+            AppError::Validation(errors) => (
+                StatusCode::BAD_REQUEST,
+                serde_json::to_value(&errors).unwrap_or_else(|_| Value::String(errors.to_string())),
+            ),
+            AppError::Timeout => (
+                StatusCode::REQUEST_TIMEOUT,
+                Value::String("request timed out".to_string()),
+            ),
+            AppError::Internal(message) => {
+                (StatusCode::INTERNAL_SERVER_ERROR, Value::String(message))
+            }
+        };
+
+        (
+            status,
+            Json(ErrorBody {
+                error,
+                code: status.as_u16(),
+            }),
+        )
+            .into_response()
+    }
+}
+
+impl From<validator::ValidationErrors> for AppError {
+    fn from(errors: validator::ValidationErrors) -> Self {
+        AppError::Validation(errors)
+    }
+}
+
+impl From<crate::repository::RepositoryError> for AppError {
+    fn from(err: crate::repository::RepositoryError) -> Self {
+        use crate::repository::RepositoryError;
+
+        match err {
+            RepositoryError::NotFound => AppError::NotFound("not found".to_string()),
+            RepositoryError::InvalidTransition => {
+                AppError::BadRequest("invalid state transition".to_string())
+            }
+            RepositoryError::InvalidReference => {
+                AppError::BadRequest("referenced label does not exist".to_string())
+            }
+            RepositoryError::Backend(err) => {
+                AppError::Internal(format!("database error: {err}"))
+            }
+        }
+    }
+}
@@ -1,37 +1,41 @@
+mod api_doc;
+mod config;
+mod error;
+mod models;
+mod repository;
+
 use axum::{
     Json, Router,
     error_handling::HandleErrorLayer,
-    extract::{Path, State},
-    http::StatusCode,
+    extract::{Path, Query, State},
+    http::{HeaderMap, HeaderValue, StatusCode},
     response::IntoResponse,
     routing::{get, patch},
 };
+use clap::Parser;
 use dotenvy::dotenv;
-use serde::{Deserialize, Serialize};
-use std::{
-    collections::HashMap,
-    sync::{Arc, RwLock},
-    time::Duration,
-};
+use std::{sync::Arc, time::Duration};
 use tower::{BoxError, ServiceBuilder};
-use tower_http::trace::TraceLayer;
+use tower_http::{cors::CorsLayer, trace::TraceLayer};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+use utoipa::OpenApi;
+use utoipa_swagger_ui::SwaggerUi;
 use uuid::Uuid;
+use validator::Validate;
 
-// TODO: ArcやRwLockとは
-type Db = Arc<RwLock<HashMap<Uuid, Todo>>>;
+use api_doc::ApiDoc;
+use config::Args;
+use error::AppError;
+use models::{CreateLabel, CreateTodo, ListParams, ToggleTask, UpdateTodo};
+use repository::{PgRepository, RepositoryError, TodoRepository};
 
-#[derive(Debug, Serialize, Clone)]
-struct Todo {
-    id: Uuid,
-    text: String,
-    completed: bool,
-}
+// TODO: ArcやRwLockとは
+type Db = Arc<dyn TodoRepository>;
 
 #[tokio::main]
 async fn main() {
     // 環境変数の読み込み
-    dotenv().expect(".env file not found");
+    dotenv().ok();
 
     // デバッグログの初期化
     // 環境変数に基づくログフィルタを設定する
@@ -45,35 +49,49 @@ async fn main() {
         .with(tracing_subscriber::fmt::layer())
         .init();
 
-    // メモリ内のデータベースを準備
-    // スレッドセーフなメモリ内データベースを構築する。これにより非同期コンテキストでのデータ読み書きが可能になる
-    let db = Db::default();
+    let args = Args::parse();
+
+    // Postgresに接続し、永続化されたデータベースを準備する
+    let db: Db = Arc::new(
+        PgRepository::connect(&args.database_url)
+            .await
+            .expect("failed to connect to database"),
+    );
+
+    let cors = CorsLayer::new().allow_origin(
+        args.cors_origins
+            .iter()
+            .map(|origin| origin.parse().expect("invalid CORS origin"))
+            .collect::<Vec<_>>(),
+    );
 
     // ルーティング設定AxumのRouterを使用してエンドポイントを登録
     // それぞれのエンドポイントに対しメソッドを登録
     let app = Router::new()
         .route("/todos", get(todos_index).post(todos_create))
         .route("/todos/:id", patch(todos_update).delete(todos_delete))
+        .route("/todos/:id/tasks", patch(todos_toggle_task))
+        .route("/labels", get(labels_index).post(labels_create))
+        .route("/labels/:id", axum::routing::delete(labels_delete))
+        .merge(SwaggerUi::new("/swagger-ui").url("/api-docs/openapi.json", ApiDoc::openapi()))
         .layer(
             ServiceBuilder::new()
                 .layer(HandleErrorLayer::new(|error: BoxError| async move {
                     if error.is::<tower::timeout::error::Elapsed>() {
-                        Ok(StatusCode::REQUEST_TIMEOUT)
+                        AppError::Timeout
                     } else {
-                        Err((
-                            StatusCode::INTERNAL_SERVER_ERROR,
-                            format!("Unhandled internal error: {error}"),
-                        ))
+                        AppError::Internal(format!("Unhandled internal error: {error}"))
                     }
                 }))
                 .timeout(Duration::from_secs(10)) // タイムアウトを10秒に設定する
                 .layer(TraceLayer::new_for_http())
+                .layer(cors)
                 .into_inner(),
         )
         .with_state(db); // TODO:ステートとは
 
     // TODO: HTTPサーバーを起動
-    let listener = tokio::net::TcpListener::bind("127.0.0.1:8080")
+    let listener = tokio::net::TcpListener::bind((args.host.as_str(), args.port))
         .await
         .unwrap();
     tracing::debug!("listening on {}", listener.local_addr().unwrap());
@@ -81,64 +99,170 @@ async fn main() {
 }
 
 // 全TODO項目の一覧をJSON形式で返します
-async fn todos_index(State(db): State<Db>) -> impl IntoResponse {
-    let todos = db.read().unwrap();
-    let todos = todos.values().cloned().collect::<Vec<_>>();
-    Json(todos)
-}
-
-#[derive(Debug, Deserialize)]
-struct CreateTodo {
-    text: String,
-}
-// 新しいTODO項目の一覧をJSOＮ形式で返す
-async fn todos_create(State(db): State<Db>, Json(input): Json<CreateTodo>) -> impl IntoResponse {
-    let todo = Todo {
-        id: Uuid::new_v4(),
-        text: input.text,
-        completed: false,
-    };
+#[utoipa::path(
+    get,
+    path = "/todos",
+    params(models::ListParams),
+    responses(
+        (status = 200, description = "List todos matching the filter, with X-Total-Count header", body = [models::Todo]),
+    )
+)]
+async fn todos_index(
+    State(db): State<Db>,
+    Query(params): Query<ListParams>,
+) -> Result<impl IntoResponse, AppError> {
+    let (todos, total) = db.list(&params).await?;
 
-    db.write().unwrap().insert(todo.id, todo.clone());
+    let mut headers = HeaderMap::new();
+    headers.insert("X-Total-Count", HeaderValue::from(total as u64));
 
-    (StatusCode::CREATED, Json(todo))
+    Ok((headers, Json(todos)))
 }
 
-#[derive(Debug, Deserialize)]
-struct UpdateTodo {
-    text: Option<String>,
-    completed: Option<bool>,
+// 新しいTODO項目を作成し、JSON形式で返す
+#[utoipa::path(
+    post,
+    path = "/todos",
+    request_body = models::CreateTodo,
+    responses(
+        (status = 201, description = "Todo created", body = models::Todo),
+        (status = 400, description = "Validation failed"),
+    )
+)]
+async fn todos_create(
+    State(db): State<Db>,
+    Json(input): Json<CreateTodo>,
+) -> Result<impl IntoResponse, AppError> {
+    input.validate()?;
+
+    let todo = db.create(input).await?;
+    Ok((StatusCode::CREATED, Json(todo)))
 }
 
+#[utoipa::path(
+    patch,
+    path = "/todos/{id}",
+    params(("id" = Uuid, Path, description = "Todo id")),
+    request_body = models::UpdateTodo,
+    responses(
+        (status = 200, description = "Todo updated", body = models::Todo),
+        (status = 400, description = "Validation failed"),
+        (status = 404, description = "Todo not found"),
+        (status = 408, description = "Request timed out"),
+        (status = 500, description = "Internal error"),
+    )
+)]
 async fn todos_update(
     Path(id): Path<Uuid>,
     State(db): State<Db>,
     Json(input): Json<UpdateTodo>,
-) -> Result<impl IntoResponse, StatusCode> {
-    let mut todo = db
-        .read()
-        .unwrap()
-        .get(&id)
-        .cloned()
-        .ok_or(StatusCode::NOT_FOUND)?;
-
-    if let Some(text) = input.text {
-        todo.text = text;
-    }
+) -> Result<impl IntoResponse, AppError> {
+    input.validate()?;
 
-    if let Some(completed) = input.completed {
-        todo.completed = completed;
-    }
+    let todo = db.update(id, input).await.map_err(|err| match err {
+        RepositoryError::NotFound => AppError::NotFound("todo not found".to_string()),
+        RepositoryError::InvalidTransition => {
+            AppError::BadRequest("invalid state transition".to_string())
+        }
+        other => other.into(),
+    })?;
+
+    Ok(Json(todo))
+}
 
-    db.write().unwrap().insert(todo.id, todo.clone());
+#[utoipa::path(
+    patch,
+    path = "/todos/{id}/tasks",
+    params(("id" = Uuid, Path, description = "Todo id")),
+    request_body = models::ToggleTask,
+    responses(
+        (status = 200, description = "Subtask toggled", body = models::Todo),
+        (status = 404, description = "Todo or subtask not found"),
+    )
+)]
+async fn todos_toggle_task(
+    Path(id): Path<Uuid>,
+    State(db): State<Db>,
+    Json(input): Json<ToggleTask>,
+) -> Result<impl IntoResponse, AppError> {
+    let todo = db
+        .toggle_task(id, input.index)
+        .await
+        .map_err(|err| match err {
+            RepositoryError::NotFound => {
+                AppError::NotFound("todo or subtask not found".to_string())
+            }
+            other => other.into(),
+        })?;
 
     Ok(Json(todo))
 }
 
-async fn todos_delete(Path(id): Path<Uuid>, State(db): State<Db>) -> impl IntoResponse {
-    if db.write().unwrap().remove(&id).is_some() {
-        StatusCode::NO_CONTENT
+#[utoipa::path(
+    delete,
+    path = "/todos/{id}",
+    params(("id" = Uuid, Path, description = "Todo id")),
+    responses(
+        (status = 204, description = "Todo deleted"),
+        (status = 404, description = "Todo not found"),
+        (status = 408, description = "Request timed out"),
+        (status = 500, description = "Internal error"),
+    )
+)]
+async fn todos_delete(
+    Path(id): Path<Uuid>,
+    State(db): State<Db>,
+) -> Result<impl IntoResponse, AppError> {
+    if db.delete(id).await? {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(AppError::NotFound("todo not found".to_string()))
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/labels",
+    responses(
+        (status = 200, description = "List all labels", body = [models::Label]),
+    )
+)]
+async fn labels_index(State(db): State<Db>) -> Result<impl IntoResponse, AppError> {
+    Ok(Json(db.list_labels().await?))
+}
+
+#[utoipa::path(
+    post,
+    path = "/labels",
+    request_body = models::CreateLabel,
+    responses(
+        (status = 201, description = "Label created", body = models::Label),
+    )
+)]
+async fn labels_create(
+    State(db): State<Db>,
+    Json(input): Json<CreateLabel>,
+) -> Result<impl IntoResponse, AppError> {
+    let label = db.create_label(input).await?;
+    Ok((StatusCode::CREATED, Json(label)))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/labels/{id}",
+    params(("id" = Uuid, Path, description = "Label id")),
+    responses(
+        (status = 204, description = "Label deleted"),
+        (status = 404, description = "Label not found"),
+    )
+)]
+async fn labels_delete(
+    Path(id): Path<Uuid>,
+    State(db): State<Db>,
+) -> Result<impl IntoResponse, AppError> {
+    if db.delete_label(id).await? {
+        Ok(StatusCode::NO_CONTENT)
     } else {
-        StatusCode::NOT_FOUND
+        Err(AppError::NotFound("label not found".to_string()))
     }
 }
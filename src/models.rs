@@ -0,0 +1,91 @@
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+use validator::Validate;
+
+/// Lifecycle of a `Todo`. Transitions between states are validated by
+/// `State::can_transition_to` rather than assigned freely.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum State {
+    Todo,
+    Doing,
+    Done,
+}
+
+impl State {
+    /// Whether moving from `self` to `next` is an allowed transition.
+    /// Work starts (`Todo` -> `Doing`), finishes (`Doing` -> `Done`), or is
+    /// reopened/paused (`Doing` -> `Todo`, `Done` -> `Doing`), and staying in
+    /// the same state is always allowed. Jumping straight from `Todo` to
+    /// `Done`, or any other combination, is rejected.
+    pub fn can_transition_to(self, next: State) -> bool {
+        use State::*;
+        self == next || matches!((self, next), (Todo, Doing) | (Doing, Done) | (Doing, Todo) | (Done, Doing))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct Subtask {
+    pub title: String,
+    pub is_done: bool,
+}
+
+#[derive(Debug, Serialize, Clone, ToSchema)]
+pub struct Todo {
+    pub id: Uuid,
+    pub text: String,
+    pub state: State,
+    pub owner: Uuid,
+    pub tasks: Vec<Subtask>,
+    pub labels: Vec<Label>,
+}
+
+#[derive(Debug, Deserialize, ToSchema, Validate)]
+pub struct CreateTodo {
+    #[validate(length(min = 1, max = 512))]
+    pub text: String,
+    pub owner: Uuid,
+    #[serde(default)]
+    pub tasks: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema, Validate)]
+pub struct UpdateTodo {
+    #[validate(length(min = 1, max = 512))]
+    pub text: Option<String>,
+    pub state: Option<State>,
+    #[serde(default)]
+    pub add_labels: Vec<Uuid>,
+    #[serde(default)]
+    pub remove_labels: Vec<Uuid>,
+}
+
+/// Toggles the `is_done` flag of the subtask at `index` in a
+/// `PATCH /todos/{id}/tasks` request.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ToggleTask {
+    pub index: usize,
+}
+
+#[derive(Debug, Serialize, Clone, ToSchema)]
+pub struct Label {
+    pub id: Uuid,
+    pub name: String,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateLabel {
+    pub name: String,
+}
+
+/// Query parameters accepted by `GET /todos`. The repository defaults `offset`
+/// to 0 and defaults and caps `limit`, so the response stays bounded as the
+/// store grows.
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct ListParams {
+    pub completed: Option<bool>,
+    pub text: Option<String>,
+    pub offset: Option<usize>,
+    pub limit: Option<usize>,
+}
@@ -0,0 +1,685 @@
+use async_trait::async_trait;
+use std::collections::{HashMap, HashSet};
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+use crate::models::{CreateLabel, CreateTodo, Label, ListParams, State, Subtask, Todo, UpdateTodo};
+
+/// Default page size for `GET /todos` when `limit` is not given.
+const DEFAULT_LIMIT: usize = 20;
+/// Upper bound on `limit`, regardless of what the client asks for.
+const MAX_LIMIT: usize = 100;
+
+/// Defaults `ListParams.offset` to 0 and `limit` to `DEFAULT_LIMIT`, capping
+/// `limit` at `MAX_LIMIT`. `offset` itself is not bounded.
+fn normalize_pagination(params: &ListParams) -> (usize, usize) {
+    let offset = params.offset.unwrap_or(0);
+    let limit = params.limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT);
+    (offset, limit)
+}
+
+/// Why a `TodoRepository` call failed.
+#[derive(Debug)]
+pub enum RepositoryError {
+    NotFound,
+    InvalidTransition,
+    /// A referenced row (e.g. a label id in `add_labels`) doesn't exist.
+    InvalidReference,
+    /// Anything else the backing store rejected; carries the underlying error
+    /// so handlers/logs still see what went wrong.
+    Backend(sqlx::Error),
+}
+
+impl From<sqlx::Error> for RepositoryError {
+    fn from(err: sqlx::Error) -> Self {
+        match err.as_database_error().and_then(|db_err| db_err.code()) {
+            Some(code) if code == "23503" => RepositoryError::InvalidReference,
+            _ => RepositoryError::Backend(err),
+        }
+    }
+}
+
+/// Storage abstraction for todos and labels. Handlers depend on this trait
+/// rather than a concrete map so the backing store can be swapped (in-memory
+/// for tests, Postgres for production) without touching routing code.
+#[async_trait]
+pub trait TodoRepository: Send + Sync {
+    /// Returns the page of todos matching `params` plus the total count of
+    /// matching todos (ignoring pagination), used for the `X-Total-Count` header.
+    async fn list(&self, params: &ListParams) -> Result<(Vec<Todo>, usize), RepositoryError>;
+    async fn create(&self, input: CreateTodo) -> Result<Todo, RepositoryError>;
+    async fn update(&self, id: Uuid, input: UpdateTodo) -> Result<Todo, RepositoryError>;
+    async fn toggle_task(&self, id: Uuid, index: usize) -> Result<Todo, RepositoryError>;
+    async fn delete(&self, id: Uuid) -> Result<bool, RepositoryError>;
+
+    async fn list_labels(&self) -> Result<Vec<Label>, RepositoryError>;
+    async fn create_label(&self, input: CreateLabel) -> Result<Label, RepositoryError>;
+    async fn delete_label(&self, id: Uuid) -> Result<bool, RepositoryError>;
+}
+
+/// The original `HashMap`-backed store, now behind `TodoRepository`.
+/// Not wired into `main`, which always talks to Postgres, but exercised by
+/// the tests below. `#[allow(dead_code)]` stays because those tests are
+/// `cfg(test)`-only, so a plain (non-test) build still never constructs this.
+#[allow(dead_code)]
+#[derive(Default)]
+pub struct InMemoryRepository {
+    todos: RwLock<HashMap<Uuid, Todo>>,
+    labels: RwLock<HashMap<Uuid, Label>>,
+    /// Todo id -> set of attached label ids.
+    todo_labels: RwLock<HashMap<Uuid, HashSet<Uuid>>>,
+}
+
+impl InMemoryRepository {
+    async fn labels_for(&self, todo_id: Uuid) -> Vec<Label> {
+        let todo_labels = self.todo_labels.read().await;
+        let labels = self.labels.read().await;
+        todo_labels
+            .get(&todo_id)
+            .into_iter()
+            .flatten()
+            .filter_map(|label_id| labels.get(label_id).cloned())
+            .collect()
+    }
+
+    async fn apply_labels(
+        &self,
+        id: Uuid,
+        add_labels: Vec<Uuid>,
+        remove_labels: Vec<Uuid>,
+    ) -> Result<(), RepositoryError> {
+        if add_labels.is_empty() && remove_labels.is_empty() {
+            return Ok(());
+        }
+
+        if !add_labels.is_empty() {
+            let labels = self.labels.read().await;
+            if add_labels
+                .iter()
+                .any(|label_id| !labels.contains_key(label_id))
+            {
+                return Err(RepositoryError::InvalidReference);
+            }
+        }
+
+        let mut todo_labels = self.todo_labels.write().await;
+        let attached = todo_labels.entry(id).or_default();
+        for label_id in add_labels {
+            attached.insert(label_id);
+        }
+        for label_id in remove_labels {
+            attached.remove(&label_id);
+        }
+        Ok(())
+    }
+
+    async fn fetch(&self, id: Uuid) -> Result<Todo, RepositoryError> {
+        let mut todo = self
+            .todos
+            .read()
+            .await
+            .get(&id)
+            .cloned()
+            .ok_or(RepositoryError::NotFound)?;
+        todo.labels = self.labels_for(id).await;
+        Ok(todo)
+    }
+}
+
+#[async_trait]
+impl TodoRepository for InMemoryRepository {
+    async fn list(&self, params: &ListParams) -> Result<(Vec<Todo>, usize), RepositoryError> {
+        let mut todos = self.todos.read().await.values().cloned().collect::<Vec<_>>();
+        todos.sort_by_key(|todo| todo.id);
+
+        if let Some(completed) = params.completed {
+            todos.retain(|todo| (todo.state == State::Done) == completed);
+        }
+        if let Some(text) = &params.text {
+            let needle = text.to_lowercase();
+            todos.retain(|todo| todo.text.to_lowercase().contains(&needle));
+        }
+
+        let total = todos.len();
+        let (offset, limit) = normalize_pagination(params);
+
+        let mut page = Vec::with_capacity(limit.min(total));
+        for mut todo in todos.into_iter().skip(offset).take(limit) {
+            todo.labels = self.labels_for(todo.id).await;
+            page.push(todo);
+        }
+
+        Ok((page, total))
+    }
+
+    async fn create(&self, input: CreateTodo) -> Result<Todo, RepositoryError> {
+        let todo = Todo {
+            id: Uuid::new_v4(),
+            text: input.text,
+            state: State::Todo,
+            owner: input.owner,
+            tasks: input
+                .tasks
+                .into_iter()
+                .map(|title| Subtask {
+                    title,
+                    is_done: false,
+                })
+                .collect(),
+            labels: Vec::new(),
+        };
+        self.todos.write().await.insert(todo.id, todo.clone());
+        self.todo_labels
+            .write()
+            .await
+            .insert(todo.id, HashSet::new());
+        Ok(todo)
+    }
+
+    async fn update(&self, id: Uuid, input: UpdateTodo) -> Result<Todo, RepositoryError> {
+        // Validate the transition before mutating anything, so a rejected
+        // PATCH (or a failed label reference, checked below) leaves the
+        // stored todo untouched, matching PgRepository's transaction.
+        {
+            let todos = self.todos.read().await;
+            let todo = todos.get(&id).ok_or(RepositoryError::NotFound)?;
+            if let Some(state) = input.state
+                && !todo.state.can_transition_to(state)
+            {
+                return Err(RepositoryError::InvalidTransition);
+            }
+        }
+
+        self.apply_labels(id, input.add_labels, input.remove_labels)
+            .await?;
+
+        {
+            let mut todos = self.todos.write().await;
+            let todo = todos.get_mut(&id).ok_or(RepositoryError::NotFound)?;
+
+            if let Some(text) = input.text {
+                todo.text = text;
+            }
+            if let Some(state) = input.state {
+                todo.state = state;
+            }
+        }
+
+        self.fetch(id).await
+    }
+
+    async fn toggle_task(&self, id: Uuid, index: usize) -> Result<Todo, RepositoryError> {
+        {
+            let mut todos = self.todos.write().await;
+            let todo = todos.get_mut(&id).ok_or(RepositoryError::NotFound)?;
+            let task = todo.tasks.get_mut(index).ok_or(RepositoryError::NotFound)?;
+            task.is_done = !task.is_done;
+        }
+
+        self.fetch(id).await
+    }
+
+    async fn delete(&self, id: Uuid) -> Result<bool, RepositoryError> {
+        self.todo_labels.write().await.remove(&id);
+        Ok(self.todos.write().await.remove(&id).is_some())
+    }
+
+    async fn list_labels(&self) -> Result<Vec<Label>, RepositoryError> {
+        Ok(self.labels.read().await.values().cloned().collect())
+    }
+
+    async fn create_label(&self, input: CreateLabel) -> Result<Label, RepositoryError> {
+        let label = Label {
+            id: Uuid::new_v4(),
+            name: input.name,
+        };
+        self.labels.write().await.insert(label.id, label.clone());
+        Ok(label)
+    }
+
+    async fn delete_label(&self, id: Uuid) -> Result<bool, RepositoryError> {
+        let removed = self.labels.write().await.remove(&id).is_some();
+        if removed {
+            for attached in self.todo_labels.write().await.values_mut() {
+                attached.remove(&id);
+            }
+        }
+        Ok(removed)
+    }
+}
+
+impl State {
+    fn as_db_str(self) -> &'static str {
+        match self {
+            State::Todo => "todo",
+            State::Doing => "doing",
+            State::Done => "done",
+        }
+    }
+
+    fn from_db_str(value: &str) -> State {
+        match value {
+            "doing" => State::Doing,
+            "done" => State::Done,
+            _ => State::Todo,
+        }
+    }
+}
+
+/// Postgres-backed store. Schema:
+/// `todo (id uuid primary key, text varchar, state varchar, owner uuid, tasks jsonb)`,
+/// `label (id uuid primary key, name varchar)`,
+/// `todo_labels (todo_id uuid references todo(id), label_id uuid references label(id), primary key (todo_id, label_id))`.
+pub struct PgRepository {
+    pool: sqlx::PgPool,
+}
+
+impl PgRepository {
+    pub async fn connect(database_url: &str) -> Result<Self, sqlx::Error> {
+        let pool = sqlx::PgPool::connect(database_url).await?;
+        Ok(Self { pool })
+    }
+
+    async fn labels_for(&self, todo_id: Uuid) -> Result<Vec<Label>, RepositoryError> {
+        let labels = sqlx::query_as!(
+            Label,
+            "SELECT label.id, label.name \
+             FROM label \
+             JOIN todo_labels ON todo_labels.label_id = label.id \
+             WHERE todo_labels.todo_id = $1",
+            todo_id,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(labels)
+    }
+
+    fn to_todo(row: TodoRow, labels: Vec<Label>) -> Todo {
+        Todo {
+            id: row.id,
+            text: row.text,
+            state: State::from_db_str(&row.state),
+            owner: row.owner,
+            tasks: row.tasks.0,
+            labels,
+        }
+    }
+}
+
+struct TodoRow {
+    id: Uuid,
+    text: String,
+    state: String,
+    owner: Uuid,
+    tasks: sqlx::types::Json<Vec<Subtask>>,
+}
+
+#[async_trait]
+impl TodoRepository for PgRepository {
+    async fn list(&self, params: &ListParams) -> Result<(Vec<Todo>, usize), RepositoryError> {
+        let like_text = params.text.as_ref().map(|text| format!("%{text}%"));
+        let (offset, limit) = normalize_pagination(params);
+
+        let total = sqlx::query_scalar!(
+            "SELECT COUNT(*) FROM todo \
+             WHERE ($1::bool IS NULL OR (state = 'done') = $1) \
+             AND ($2::text IS NULL OR text ILIKE $2)",
+            params.completed,
+            like_text,
+        )
+        .fetch_one(&self.pool)
+        .await?
+        .unwrap_or(0);
+
+        let rows = sqlx::query_as!(
+            TodoRow,
+            r#"SELECT id, text, state, owner, tasks as "tasks: sqlx::types::Json<Vec<Subtask>>" FROM todo
+             WHERE ($1::bool IS NULL OR (state = 'done') = $1)
+             AND ($2::text IS NULL OR text ILIKE $2)
+             ORDER BY id
+             OFFSET $3 LIMIT $4"#,
+            params.completed,
+            like_text,
+            offset as i64,
+            limit as i64,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut todos = Vec::with_capacity(rows.len());
+        for row in rows {
+            let labels = self.labels_for(row.id).await?;
+            todos.push(Self::to_todo(row, labels));
+        }
+        Ok((todos, total as usize))
+    }
+
+    async fn create(&self, input: CreateTodo) -> Result<Todo, RepositoryError> {
+        let id = Uuid::new_v4();
+        let text = input.text;
+        let tasks: Vec<Subtask> = input
+            .tasks
+            .into_iter()
+            .map(|title| Subtask {
+                title,
+                is_done: false,
+            })
+            .collect();
+
+        sqlx::query!(
+            "INSERT INTO todo (id, text, state, owner, tasks) VALUES ($1, $2, $3, $4, $5)",
+            id,
+            &text,
+            State::Todo.as_db_str(),
+            input.owner,
+            sqlx::types::Json(&tasks) as _,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(Todo {
+            id,
+            text,
+            state: State::Todo,
+            owner: input.owner,
+            tasks,
+            labels: Vec::new(),
+        })
+    }
+
+    async fn update(&self, id: Uuid, input: UpdateTodo) -> Result<Todo, RepositoryError> {
+        let mut tx = self.pool.begin().await?;
+
+        let row = sqlx::query_as!(
+            TodoRow,
+            r#"SELECT id, text, state, owner, tasks as "tasks: sqlx::types::Json<Vec<Subtask>>" FROM todo WHERE id = $1"#,
+            id,
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or(RepositoryError::NotFound)?;
+
+        let current_state = State::from_db_str(&row.state);
+        let next_state = match input.state {
+            Some(state) => {
+                if !current_state.can_transition_to(state) {
+                    return Err(RepositoryError::InvalidTransition);
+                }
+                state
+            }
+            None => current_state,
+        };
+        let text = input.text.unwrap_or(row.text);
+
+        sqlx::query!(
+            "UPDATE todo SET text = $2, state = $3 WHERE id = $1",
+            id,
+            &text,
+            next_state.as_db_str(),
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        for label_id in input.add_labels {
+            sqlx::query!(
+                "INSERT INTO todo_labels (todo_id, label_id) VALUES ($1, $2) \
+                 ON CONFLICT DO NOTHING",
+                id,
+                label_id,
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+        for label_id in input.remove_labels {
+            sqlx::query!(
+                "DELETE FROM todo_labels WHERE todo_id = $1 AND label_id = $2",
+                id,
+                label_id,
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+
+        let labels = self.labels_for(id).await?;
+
+        Ok(Todo {
+            id,
+            text,
+            state: next_state,
+            owner: row.owner,
+            tasks: row.tasks.0,
+            labels,
+        })
+    }
+
+    async fn toggle_task(&self, id: Uuid, index: usize) -> Result<Todo, RepositoryError> {
+        let row = sqlx::query_as!(
+            TodoRow,
+            r#"SELECT id, text, state, owner, tasks as "tasks: sqlx::types::Json<Vec<Subtask>>" FROM todo WHERE id = $1"#,
+            id,
+        )
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or(RepositoryError::NotFound)?;
+
+        let mut tasks = row.tasks.0;
+        let task = tasks.get_mut(index).ok_or(RepositoryError::NotFound)?;
+        task.is_done = !task.is_done;
+
+        sqlx::query!(
+            "UPDATE todo SET tasks = $2 WHERE id = $1",
+            id,
+            sqlx::types::Json(&tasks) as _,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        let labels = self.labels_for(id).await?;
+
+        Ok(Todo {
+            id,
+            text: row.text,
+            state: State::from_db_str(&row.state),
+            owner: row.owner,
+            tasks,
+            labels,
+        })
+    }
+
+    async fn delete(&self, id: Uuid) -> Result<bool, RepositoryError> {
+        let result = sqlx::query!("DELETE FROM todo WHERE id = $1", id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn list_labels(&self) -> Result<Vec<Label>, RepositoryError> {
+        let labels = sqlx::query_as!(Label, "SELECT id, name FROM label")
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(labels)
+    }
+
+    async fn create_label(&self, input: CreateLabel) -> Result<Label, RepositoryError> {
+        let id = Uuid::new_v4();
+        sqlx::query!("INSERT INTO label (id, name) VALUES ($1, $2)", id, &input.name)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(Label {
+            id,
+            name: input.name,
+        })
+    }
+
+    async fn delete_label(&self, id: Uuid) -> Result<bool, RepositoryError> {
+        let result = sqlx::query!("DELETE FROM label WHERE id = $1", id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_input(text: &str) -> CreateTodo {
+        CreateTodo {
+            text: text.to_string(),
+            owner: Uuid::new_v4(),
+            tasks: Vec::new(),
+        }
+    }
+
+    fn update_input() -> UpdateTodo {
+        UpdateTodo {
+            text: None,
+            state: None,
+            add_labels: Vec::new(),
+            remove_labels: Vec::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn create_then_list_returns_the_todo() {
+        let repo = InMemoryRepository::default();
+        let created = repo.create(create_input("write tests")).await.unwrap();
+
+        let (todos, total) = repo.list(&ListParams {
+            completed: None,
+            text: None,
+            offset: None,
+            limit: None,
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(total, 1);
+        assert_eq!(todos.len(), 1);
+        assert_eq!(todos[0].id, created.id);
+        assert_eq!(todos[0].text, "write tests");
+    }
+
+    #[tokio::test]
+    async fn update_changes_text_and_allows_valid_transition() {
+        let repo = InMemoryRepository::default();
+        let todo = repo.create(create_input("draft")).await.unwrap();
+
+        let updated = repo
+            .update(
+                todo.id,
+                UpdateTodo {
+                    text: Some("final".to_string()),
+                    state: Some(State::Doing),
+                    ..update_input()
+                },
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(updated.text, "final");
+        assert_eq!(updated.state, State::Doing);
+    }
+
+    #[tokio::test]
+    async fn update_rejects_invalid_transition() {
+        let repo = InMemoryRepository::default();
+        let todo = repo.create(create_input("draft")).await.unwrap();
+
+        let err = repo
+            .update(
+                todo.id,
+                UpdateTodo {
+                    state: Some(State::Done),
+                    ..update_input()
+                },
+            )
+            .await
+            .unwrap_err();
+
+        assert!(matches!(err, RepositoryError::InvalidTransition));
+    }
+
+    #[tokio::test]
+    async fn update_rejects_invalid_transition_without_mutating_text() {
+        let repo = InMemoryRepository::default();
+        let todo = repo.create(create_input("draft")).await.unwrap();
+
+        let err = repo
+            .update(
+                todo.id,
+                UpdateTodo {
+                    text: Some("final".to_string()),
+                    state: Some(State::Done),
+                    ..update_input()
+                },
+            )
+            .await
+            .unwrap_err();
+
+        assert!(matches!(err, RepositoryError::InvalidTransition));
+
+        let (todos, _) = repo
+            .list(&ListParams {
+                completed: None,
+                text: None,
+                offset: None,
+                limit: None,
+            })
+            .await
+            .unwrap();
+        assert_eq!(todos[0].text, "draft");
+        assert_eq!(todos[0].state, State::Todo);
+    }
+
+    #[tokio::test]
+    async fn update_on_missing_todo_is_not_found() {
+        let repo = InMemoryRepository::default();
+
+        let err = repo.update(Uuid::new_v4(), update_input()).await.unwrap_err();
+
+        assert!(matches!(err, RepositoryError::NotFound));
+    }
+
+    #[tokio::test]
+    async fn labels_attach_and_detach() {
+        let repo = InMemoryRepository::default();
+        let todo = repo.create(create_input("tag me")).await.unwrap();
+        let label = repo
+            .create_label(CreateLabel {
+                name: "urgent".to_string(),
+            })
+            .await
+            .unwrap();
+
+        let attached = repo
+            .update(
+                todo.id,
+                UpdateTodo {
+                    add_labels: vec![label.id],
+                    ..update_input()
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(attached.labels.len(), 1);
+        assert_eq!(attached.labels[0].id, label.id);
+
+        let detached = repo
+            .update(
+                todo.id,
+                UpdateTodo {
+                    remove_labels: vec![label.id],
+                    ..update_input()
+                },
+            )
+            .await
+            .unwrap();
+        assert!(detached.labels.is_empty());
+    }
+}